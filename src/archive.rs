@@ -7,6 +7,7 @@ use serde_big_array::BigArray;
 
 use nom::number::complete::be_u24;
 
+use crate::error::MetadataError;
 use crate::sector::{
     SectorHeaderSize, SECTOR_DATA_SIZE, SECTOR_EXPANDED_DATA_SIZE, SECTOR_EXPANDED_HEADER_SIZE,
     SECTOR_HEADER_SIZE,
@@ -119,6 +120,147 @@ pub struct ArchiveMetadata {
     pub valid_ids: Vec<u32>,
 }
 
+impl ArchiveMetadata {
+    /// Creates a builder to construct a well-formed `ArchiveMetadata`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use runefs::ArchiveMetadata;
+    ///
+    /// # fn main() -> Result<(), runefs::Error> {
+    /// let metadata = ArchiveMetadata::builder()
+    ///     .id(10)
+    ///     .crc(0x1234)
+    ///     .version(3)
+    ///     .valid_ids(vec![0, 1, 4])
+    ///     .build()?;
+    ///
+    /// assert_eq!(metadata.entry_count, 3);
+    /// assert_eq!(metadata.valid_ids, vec![0, 1, 4]);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn builder() -> ArchiveMetadataBuilder {
+        ArchiveMetadataBuilder::default()
+    }
+
+    /// Checks if the entry count and valid ids of this archive agree with each other.
+    ///
+    /// # Errors
+    ///
+    /// Fails when `entry_count` differs from the amount of `valid_ids` or when the ids
+    /// are not strictly ascending, which would make them impossible to delta-encode.
+    pub fn validate(&self) -> Result<(), MetadataError> {
+        if self.entry_count != self.valid_ids.len() {
+            return Err(MetadataError::EntryCountMismatch {
+                arc: self.id,
+                count: self.entry_count,
+                ids: self.valid_ids.len(),
+            });
+        }
+
+        for pair in self.valid_ids.windows(2) {
+            if pair[1] <= pair[0] {
+                return Err(MetadataError::EntryIdOrder {
+                    arc: self.id,
+                    id: pair[1],
+                });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Builder for [`ArchiveMetadata`](ArchiveMetadata).
+///
+/// Only the id is required, every other field defaults to zero. When no explicit entry count
+/// is given it is derived from the valid ids.
+#[derive(Clone, Debug, Default)]
+pub struct ArchiveMetadataBuilder {
+    id: Option<u32>,
+    name_hash: i32,
+    crc: u32,
+    hash: i32,
+    whirlpool: Option<[u8; 64]>,
+    version: u32,
+    entry_count: Option<usize>,
+    valid_ids: Vec<u32>,
+}
+
+impl ArchiveMetadataBuilder {
+    pub const fn id(mut self, id: u32) -> Self {
+        self.id = Some(id);
+        self
+    }
+
+    pub const fn name_hash(mut self, name_hash: i32) -> Self {
+        self.name_hash = name_hash;
+        self
+    }
+
+    pub const fn crc(mut self, crc: u32) -> Self {
+        self.crc = crc;
+        self
+    }
+
+    pub const fn hash(mut self, hash: i32) -> Self {
+        self.hash = hash;
+        self
+    }
+
+    pub const fn whirlpool(mut self, whirlpool: [u8; 64]) -> Self {
+        self.whirlpool = Some(whirlpool);
+        self
+    }
+
+    pub const fn version(mut self, version: u32) -> Self {
+        self.version = version;
+        self
+    }
+
+    pub const fn entry_count(mut self, entry_count: usize) -> Self {
+        self.entry_count = Some(entry_count);
+        self
+    }
+
+    /// Sets the valid entry ids, these are the absolute ids and not the delta-encoded ones.
+    pub fn valid_ids(mut self, valid_ids: Vec<u32>) -> Self {
+        self.valid_ids = valid_ids;
+        self
+    }
+
+    /// Appends a single valid entry id.
+    pub fn entry(mut self, id: u32) -> Self {
+        self.valid_ids.push(id);
+        self
+    }
+
+    /// Validates and constructs the `ArchiveMetadata`.
+    ///
+    /// # Errors
+    ///
+    /// Fails when no id was set or when [`ArchiveMetadata::validate`](ArchiveMetadata::validate)
+    /// rejects the entries.
+    pub fn build(self) -> Result<ArchiveMetadata, MetadataError> {
+        let id = self.id.ok_or(MetadataError::MissingId)?;
+        let metadata = ArchiveMetadata {
+            id,
+            name_hash: self.name_hash,
+            crc: self.crc,
+            hash: self.hash,
+            whirlpool: self.whirlpool.unwrap_or([0; 64]),
+            version: self.version,
+            entry_count: self.entry_count.unwrap_or(self.valid_ids.len()),
+            valid_ids: self.valid_ids,
+        };
+        metadata.validate()?;
+
+        Ok(metadata)
+    }
+}
+
 /// Holds an archive file id with its data.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
@@ -128,57 +270,161 @@ pub struct ArchiveFileData {
 }
 
 /// Holds all of the archive files that belong to a single archive.
+///
+/// Serializes as a plain list of files, the chunk count is not part of it. For the same
+/// reason two groups are equal when their files are, regardless of their chunk count.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
-pub struct ArchiveFileGroup(Vec<ArchiveFileData>);
+#[cfg_attr(
+    feature = "serde",
+    serde(from = "Vec<ArchiveFileData>", into = "Vec<ArchiveFileData>")
+)]
+#[derive(Clone, Debug)]
+pub struct ArchiveFileGroup {
+    files: Vec<ArchiveFileData>,
+    chunks: usize,
+}
+
+impl Default for ArchiveFileGroup {
+    fn default() -> Self {
+        Self::from(Vec::new())
+    }
+}
+
+impl PartialEq for ArchiveFileGroup {
+    fn eq(&self, other: &Self) -> bool {
+        self.files == other.files
+    }
+}
+
+impl Eq for ArchiveFileGroup {}
+
+impl PartialOrd for ArchiveFileGroup {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for ArchiveFileGroup {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.files.cmp(&other.files)
+    }
+}
+
+impl std::hash::Hash for ArchiveFileGroup {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.files.hash(state);
+    }
+}
 
 impl ArchiveFileGroup {
     /// Format a raw buffer into a list of `ArchiveFileData`'s.
-    /// 
+    ///
+    /// Larger groups are striped over multiple chunks, every chunk holds a slice of each
+    /// file in entry order. The trailing length table stores the delta-encoded slice sizes
+    /// per chunk, followed by a single byte with the amount of chunks. Each file is
+    /// reassembled by concatenating its slice from every chunk.
+    ///
     /// # Panics
-    /// 
-    /// Whenever the buffer has a wrong format no files can be constructed.
+    ///
+    /// Whenever the buffer has a wrong format no files can be constructed. This includes a
+    /// length table with slices that fall outside of the data that precedes it.
     pub fn from_buffer(buffer: &[u8], entry_count: usize) -> Self {
-        let chunks = buffer[buffer.len() - 1] as usize;
-        let mut data = Vec::with_capacity(chunks);
-        let mut cached_chunks = Vec::with_capacity(chunks);
-        let mut read_ptr = buffer.len() - 1 - chunks * entry_count * 4;
+        let chunks = *buffer.last().expect("group buffer is empty") as usize;
+        let table_len = chunks
+            .checked_mul(entry_count)
+            .and_then(|len| len.checked_mul(4))
+            .expect("group length table is too large");
+        let data_len = buffer
+            .len()
+            .checked_sub(table_len + 1)
+            .expect("group length table exceeds the buffer");
+        let mut read_ptr = data_len;
+        let mut slice_sizes = Vec::with_capacity(chunks * entry_count);
+        let mut file_sizes = vec![0; entry_count];
+        let mut total_size = 0_usize;
 
         for _ in 0..chunks {
-            let mut chunk_size = 0;
+            let mut chunk_size = 0_i32;
 
-            for entry_id in 0..entry_count {
+            for file_size in file_sizes.iter_mut() {
                 let mut bytes = [0; 4];
                 bytes.copy_from_slice(&buffer[read_ptr..read_ptr + 4]);
                 let delta = i32::from_be_bytes(bytes);
                 read_ptr += 4;
-                chunk_size += delta;
-
-                cached_chunks.push((entry_id as u32, chunk_size as usize));
+                chunk_size = chunk_size
+                    .checked_add(delta)
+                    .expect("group slice size overflowed");
+
+                let slice_size = usize::try_from(chunk_size).expect("group slice size is negative");
+                total_size = total_size
+                    .checked_add(slice_size)
+                    .filter(|&total| total <= data_len)
+                    .expect("group slices exceed the data region");
+
+                slice_sizes.push(slice_size);
+                *file_size += slice_size;
             }
         }
-        read_ptr = 0;
-        for (entry_id, chunk_size) in cached_chunks {
-            let buf = buffer[read_ptr..read_ptr + chunk_size].to_vec();
 
-            data.push(ArchiveFileData {
-                id: entry_id,
-                data: buf,
-            });
-            read_ptr += chunk_size;
+        let mut data: Vec<Vec<u8>> = file_sizes.into_iter().map(Vec::with_capacity).collect();
+        read_ptr = 0;
+        for (slice, slice_size) in slice_sizes.into_iter().enumerate() {
+            let file = &mut data[slice % entry_count];
+            file.extend_from_slice(&buffer[read_ptr..read_ptr + slice_size]);
+            read_ptr += slice_size;
         }
 
-        Self(data)
+        let files = data
+            .into_iter()
+            .enumerate()
+            .map(|(entry_id, data)| ArchiveFileData {
+                id: entry_id as u32,
+                data,
+            })
+            .collect();
+
+        Self { files, chunks }
+    }
+
+    /// The amount of chunks the files were striped over.
+    ///
+    /// Decoded groups report the count stored in their buffer. Groups that were not decoded
+    /// from a buffer, such as default or deserialized ones, report a single chunk.
+    #[inline]
+    pub const fn chunks(&self) -> usize {
+        self.chunks
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
     }
 
     #[inline]
     pub fn iter(&self) -> Iter<'_, ArchiveFileData> {
-        self.0.iter()
+        self.files.iter()
     }
 
     #[inline]
     pub fn iter_mut(&mut self) -> IterMut<'_, ArchiveFileData> {
-        self.0.iter_mut()
+        self.files.iter_mut()
+    }
+}
+
+impl From<Vec<ArchiveFileData>> for ArchiveFileGroup {
+    fn from(files: Vec<ArchiveFileData>) -> Self {
+        Self { files, chunks: 1 }
+    }
+}
+
+impl From<ArchiveFileGroup> for Vec<ArchiveFileData> {
+    fn from(group: ArchiveFileGroup) -> Self {
+        group.files
     }
 }
 
@@ -188,7 +434,7 @@ impl IntoIterator for ArchiveFileGroup {
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        self.0.into_iter()
+        self.files.into_iter()
     }
 }
 
@@ -198,7 +444,7 @@ impl<'a> IntoIterator for &'a ArchiveFileGroup {
 
     #[inline]
     fn into_iter(self) -> Self::IntoIter {
-        self.0.iter()
+        self.files.iter()
     }
 }
 
@@ -219,3 +465,109 @@ fn parse_archive() -> crate::Result<()> {
 
     Ok(())
 }
+
+#[test]
+fn build_archive_metadata() -> crate::Result<()> {
+    let metadata = ArchiveMetadata::builder()
+        .id(7)
+        .version(2)
+        .entry(0)
+        .entry(3)
+        .entry(4)
+        .build()?;
+
+    assert_eq!(metadata.id, 7);
+    assert_eq!(metadata.entry_count, 3);
+    assert_eq!(metadata.valid_ids, vec![0, 3, 4]);
+    assert_eq!(metadata.whirlpool, [0; 64]);
+
+    Ok(())
+}
+
+#[test]
+fn build_archive_metadata_invalid() {
+    assert_eq!(
+        ArchiveMetadata::builder().build(),
+        Err(MetadataError::MissingId)
+    );
+    assert_eq!(
+        ArchiveMetadata::builder()
+            .id(1)
+            .entry_count(3)
+            .valid_ids(vec![0, 1])
+            .build(),
+        Err(MetadataError::EntryCountMismatch {
+            arc: 1,
+            count: 3,
+            ids: 2
+        })
+    );
+    assert_eq!(
+        ArchiveMetadata::builder()
+            .id(1)
+            .valid_ids(vec![0, 2, 2])
+            .build(),
+        Err(MetadataError::EntryIdOrder { arc: 1, id: 2 })
+    );
+}
+
+#[test]
+fn group_single_chunk() {
+    let buffer = &[1, 2, 3, 4, 5, 0, 0, 0, 2, 0, 0, 0, 1, 1];
+    let group = ArchiveFileGroup::from_buffer(buffer, 2);
+    let files: Vec<&[u8]> = group.iter().map(|file| file.data.as_slice()).collect();
+
+    assert_eq!(group.chunks(), 1);
+    assert_eq!(files, vec![&[1, 2][..], &[3, 4, 5][..]]);
+}
+
+#[test]
+fn group_multi_chunk() {
+    let buffer = &[
+        1, 2, 10, 3, 11, 12, 4, 13, 0, 0, 0, 2, 255, 255, 255, 255, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0,
+        0, 1, 0, 0, 0, 0, 3,
+    ];
+    let group = ArchiveFileGroup::from_buffer(buffer, 2);
+    let files: Vec<&[u8]> = group.iter().map(|file| file.data.as_slice()).collect();
+
+    assert_eq!(group.chunks(), 3);
+    assert_eq!(group.len(), 2);
+    assert_eq!(files, vec![&[1, 2, 3, 4][..], &[10, 11, 12, 13][..]]);
+}
+
+#[test]
+fn group_equality_ignores_chunks() {
+    let buffer = &[
+        1, 2, 10, 3, 11, 12, 4, 13, 0, 0, 0, 2, 255, 255, 255, 255, 0, 0, 0, 1, 0, 0, 0, 1, 0, 0,
+        0, 1, 0, 0, 0, 0, 3,
+    ];
+    let decoded = ArchiveFileGroup::from_buffer(buffer, 2);
+    let files: Vec<ArchiveFileData> = decoded.clone().into();
+    let rebuilt = ArchiveFileGroup::from(files);
+
+    assert_eq!(decoded.chunks(), 3);
+    assert_eq!(rebuilt.chunks(), 1);
+    assert_eq!(decoded, rebuilt);
+    assert_eq!(ArchiveFileGroup::default().chunks(), 1);
+}
+
+#[test]
+#[should_panic(expected = "group slices exceed the data region")]
+fn group_slices_out_of_bounds() {
+    let buffer = &[1, 2, 127, 255, 255, 255, 1];
+    ArchiveFileGroup::from_buffer(buffer, 1);
+}
+
+#[test]
+#[should_panic(expected = "group slice size is negative")]
+fn group_negative_slice() {
+    let buffer = &[1, 2, 255, 255, 255, 255, 1];
+    ArchiveFileGroup::from_buffer(buffer, 1);
+}
+
+#[test]
+#[should_panic(expected = "group length table exceeds the buffer")]
+fn group_table_out_of_bounds() {
+    let buffer = &[0, 0, 0, 1, 255];
+    ArchiveFileGroup::from_buffer(buffer, 1);
+}
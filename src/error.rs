@@ -1,6 +1,6 @@
 //! Error management.
 
-use std::io;
+use std::{io, path::PathBuf};
 use thiserror::Error;
 
 pub(crate) type Result<T> = std::result::Result<T, Error>;
@@ -14,6 +14,8 @@ pub enum Error {
     #[error(transparent)]
     Read(#[from] ReadError),
     #[error(transparent)]
+    Metadata(#[from] MetadataError),
+    #[error(transparent)]
     Compression(#[from] CompressionUnsupported),
     /// Clarification error for failed parsers.
     #[error(transparent)]
@@ -29,6 +31,8 @@ impl From<nom::Err<()>> for Error {
 
 #[derive(Error, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub enum ReadError {
+    #[error("no cache found in {}", .0.display())]
+    CacheNotFound(PathBuf),
     #[error("index {0} not found")]
     IndexNotFound(u8),
     #[error("index {idx} does not contain archive group {arc}")]
@@ -43,6 +47,26 @@ pub enum ReadError {
     SectorIndexMismatch(u8, u8),
 }
 
+#[derive(Error, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum MetadataError {
+    #[error("archive metadata is missing an id")]
+    MissingId,
+    #[error("archive {arc} has an entry count of {count} but lists {ids} valid ids")]
+    EntryCountMismatch { arc: u32, count: usize, ids: usize },
+    #[error("archive {arc} lists entry id {id} out of order or more than once")]
+    EntryIdOrder { arc: u32, id: u32 },
+    #[error("archive {0} is already present in the index metadata")]
+    DuplicateArchive(u32),
+    #[error("archive {arc} cannot be pushed after archive {last}, ids must be ascending")]
+    ArchiveOrder { arc: u32, last: u32 },
+    #[error("archive {arc} {flag} presence must be {expected} to match the other archives")]
+    FlagMismatch {
+        arc: u32,
+        flag: &'static str,
+        expected: bool,
+    },
+}
+
 #[derive(Error, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[error("unsupported compression type {0}")]
 pub struct CompressionUnsupported(pub(crate) u8);
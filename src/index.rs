@@ -11,7 +11,8 @@ use serde::{Deserialize, Serialize};
 
 use crate::{
     archive::{ArchiveMetadata, ArchiveRef, ARCHIVE_REF_LEN},
-    error::{ParseError, ReadError},
+    error::{MetadataError, ParseError, ReadError},
+    locator::{CacheFiles, CacheLocator},
     Dat2, REFERENCE_TABLE_ID,
 };
 use itertools::izip;
@@ -25,7 +26,7 @@ use nom::{
 use crate::codec::{Buffer, Decoded};
 use crate::parse::be_u32_smart;
 
-pub const IDX_PREFIX: &str = "main_file_cache.idx";
+pub const IDX_PREFIX: &str = concat!(cache_prefix!(), ".idx");
 
 /// A list of valid indices.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
@@ -36,7 +37,9 @@ impl Indices {
     /// Allocates an `Index` for every valid index file in the cache directory.
     ///
     /// An index is considered _valid_ if it is present, meaning it will scan the directory
-    /// for the `.idx#` suffix and load them into memory.
+    /// for the `.idx#` suffix and load them into memory. The directory is probed with a default
+    /// [`CacheLocator`](crate::CacheLocator), use [`from_files`](Indices::from_files) with a
+    /// configured locator for caches with a different layout or naming scheme.
     ///
     /// # Errors
     ///
@@ -50,37 +53,38 @@ impl Indices {
     /// If an index is found it needs to load its entire contents and parse it, failure at this point
     /// is considered a bug.
     pub fn new<P: AsRef<Path>>(path: P) -> crate::Result<Self> {
-        let path = path.as_ref();
+        Self::from_files(&CacheLocator::new().locate(path)?)
+    }
 
-        let ref_index = Index::from_path(
-            REFERENCE_TABLE_ID,
-            path.join(format!("{}{}", IDX_PREFIX, REFERENCE_TABLE_ID)),
-        )?;
-        let dat2 = Dat2::new(path.join(crate::MAIN_DATA))?;
-        let mut indices = HashMap::with_capacity(255);
-
-        for p in std::fs::read_dir(path)? {
-            let path = p?.path();
-
-            if let Some(ext) = path.extension().and_then(std::ffi::OsStr::to_str) {
-                if let Some(index_id) = ext.strip_prefix("idx") {
-                    let index_id: u8 = index_id.parse().expect("invalid extension format");
-                    if index_id == 255 {
-                        continue;
-                    }
-                    let mut index = Index::from_path(index_id, path)?;
-                    let archive_ref = ref_index.archive_refs.get(&(index_id as u32)).ok_or(
-                        ReadError::ArchiveNotFound {
-                            idx: REFERENCE_TABLE_ID,
-                            arc: index_id as u32,
-                        },
-                    )?;
-                    if archive_ref.length != 0 {
-                        index.metadata = dat2.metadata(archive_ref)?;
-                    }
-                    indices.insert(index_id, index);
-                }
+    /// Allocates an `Index` for every index file found by a [`CacheLocator`](crate::CacheLocator).
+    ///
+    /// # Errors
+    ///
+    /// See [`new`](Indices::new).
+    pub fn from_files(files: &CacheFiles) -> crate::Result<Self> {
+        let ref_path = files
+            .indices
+            .get(&REFERENCE_TABLE_ID)
+            .ok_or_else(|| ReadError::CacheNotFound(files.root.clone()))?;
+        let ref_index = Index::from_path(REFERENCE_TABLE_ID, ref_path)?;
+        let dat2 = Dat2::new(&files.dat2)?;
+        let mut indices = HashMap::with_capacity(files.indices.len());
+
+        for (&index_id, path) in &files.indices {
+            if index_id == REFERENCE_TABLE_ID {
+                continue;
+            }
+            let mut index = Index::from_path(index_id, path)?;
+            let archive_ref = ref_index.archive_refs.get(&(index_id as u32)).ok_or(
+                ReadError::ArchiveNotFound {
+                    idx: REFERENCE_TABLE_ID,
+                    arc: index_id as u32,
+                },
+            )?;
+            if archive_ref.length != 0 {
+                index.metadata = dat2.metadata(archive_ref)?;
             }
+            indices.insert(index_id, index);
         }
 
         indices.insert(REFERENCE_TABLE_ID, ref_index);
@@ -125,7 +129,7 @@ impl Index {
             .and_then(std::ffi::OsStr::to_str)
             .unwrap_or("nothing");
 
-        if extension != index_extension {
+        if !extension.eq_ignore_ascii_case(&index_extension) {
             panic!("index extension mismatch: expected {index_extension} but found {extension}");
         }
 
@@ -239,10 +243,100 @@ impl IndexMetadata {
         Ok(Self(archives))
     }
 
+    /// Appends an archive to the end of the metadata.
+    ///
+    /// Archive ids are delta-encoded so the pushed archive must have a higher id than
+    /// the last archive present.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the archive id is not greater than the last id, when the archive
+    /// itself does not pass [`ArchiveMetadata::validate`](ArchiveMetadata::validate) or
+    /// when its flags are inconsistent with the other archives, see
+    /// [`insert`](IndexMetadata::insert).
+    pub fn push(&mut self, archive: ArchiveMetadata) -> Result<(), MetadataError> {
+        archive.validate()?;
+        self.validate_flags(&archive)?;
+
+        if let Some(last) = self.0.last() {
+            if archive.id == last.id {
+                return Err(MetadataError::DuplicateArchive(archive.id));
+            }
+            if archive.id < last.id {
+                return Err(MetadataError::ArchiveOrder {
+                    arc: archive.id,
+                    last: last.id,
+                });
+            }
+        }
+        self.0.push(archive);
+
+        Ok(())
+    }
+
+    /// Inserts an archive at its ordered position.
+    ///
+    /// The name hash, hash and whirlpool flags are stored once for the entire index, so
+    /// either every archive has one set or none of them do. A value of zero is treated
+    /// as absent.
+    ///
+    /// # Errors
+    ///
+    /// Fails when an archive with the same id is already present, when the archive
+    /// itself does not pass [`ArchiveMetadata::validate`](ArchiveMetadata::validate) or
+    /// when its flags are inconsistent with the other archives.
+    pub fn insert(&mut self, archive: ArchiveMetadata) -> Result<(), MetadataError> {
+        archive.validate()?;
+        self.validate_flags(&archive)?;
+
+        match self.0.binary_search_by_key(&archive.id, |metadata| metadata.id) {
+            Ok(_) => Err(MetadataError::DuplicateArchive(archive.id)),
+            Err(position) => {
+                self.0.insert(position, archive);
+                Ok(())
+            }
+        }
+    }
+
+    fn validate_flags(&self, archive: &ArchiveMetadata) -> Result<(), MetadataError> {
+        let other = match self.0.first() {
+            Some(other) => other,
+            None => return Ok(()),
+        };
+
+        let whirlpool = |metadata: &ArchiveMetadata| metadata.whirlpool != [0; 64];
+        let flags = [
+            ("name hash", archive.name_hash != 0, other.name_hash != 0),
+            ("hash", archive.hash != 0, other.hash != 0),
+            ("whirlpool", whirlpool(archive), whirlpool(other)),
+        ];
+        for (flag, present, expected) in flags {
+            if present != expected {
+                return Err(MetadataError::FlagMismatch {
+                    arc: archive.id,
+                    flag,
+                    expected,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
     #[inline]
     pub fn iter(&self) -> Iter<'_, ArchiveMetadata> {
         self.0.iter()
     }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
 }
 
 impl std::ops::Index<usize> for IndexMetadata {
@@ -400,3 +494,73 @@ fn parse_entry_counts(
 
     Ok((buffer, entry_counts))
 }
+
+#[test]
+fn push_archive_metadata() -> crate::Result<()> {
+    let mut metadata = IndexMetadata::default();
+    metadata.push(ArchiveMetadata::builder().id(1).build()?)?;
+    metadata.push(ArchiveMetadata::builder().id(5).build()?)?;
+
+    assert_eq!(
+        metadata.push(ArchiveMetadata::builder().id(5).build()?),
+        Err(MetadataError::DuplicateArchive(5))
+    );
+    assert_eq!(
+        metadata.push(ArchiveMetadata::builder().id(3).build()?),
+        Err(MetadataError::ArchiveOrder { arc: 3, last: 5 })
+    );
+    assert_eq!(metadata.len(), 2);
+
+    Ok(())
+}
+
+#[test]
+fn insert_archive_metadata() -> crate::Result<()> {
+    let mut metadata = IndexMetadata::default();
+    metadata.insert(ArchiveMetadata::builder().id(5).build()?)?;
+    metadata.insert(ArchiveMetadata::builder().id(1).build()?)?;
+    metadata.insert(ArchiveMetadata::builder().id(3).build()?)?;
+
+    assert_eq!(
+        metadata.insert(ArchiveMetadata::builder().id(3).build()?),
+        Err(MetadataError::DuplicateArchive(3))
+    );
+
+    let ids: Vec<u32> = metadata.iter().map(|archive| archive.id).collect();
+    assert_eq!(ids, vec![1, 3, 5]);
+
+    Ok(())
+}
+
+#[test]
+fn archive_metadata_flag_coherence() -> crate::Result<()> {
+    let mut metadata = IndexMetadata::default();
+    metadata.push(ArchiveMetadata::builder().id(1).name_hash(-42).build()?)?;
+
+    assert_eq!(
+        metadata.push(ArchiveMetadata::builder().id(2).build()?),
+        Err(MetadataError::FlagMismatch {
+            arc: 2,
+            flag: "name hash",
+            expected: true
+        })
+    );
+    assert_eq!(
+        metadata.insert(
+            ArchiveMetadata::builder()
+                .id(0)
+                .name_hash(7)
+                .whirlpool([1; 64])
+                .build()?
+        ),
+        Err(MetadataError::FlagMismatch {
+            arc: 0,
+            flag: "whirlpool",
+            expected: false
+        })
+    );
+    metadata.insert(ArchiveMetadata::builder().id(0).name_hash(7).build()?)?;
+    assert_eq!(metadata.len(), 2);
+
+    Ok(())
+}
@@ -19,11 +19,19 @@
     clippy::perf
 )]
 
+macro_rules! cache_prefix {
+    () => {
+        "main_file_cache"
+    };
+}
+
 mod archive;
 pub mod codec;
 pub mod error;
 mod index;
+mod locator;
 pub mod parse;
+mod prefetch;
 mod sector;
 pub mod xtea;
 
@@ -31,12 +39,15 @@ pub mod xtea;
 pub use error::Error;
 use error::Result;
 
-pub const MAIN_DATA: &str = "main_file_cache.dat2";
-pub const REFERENCE_TABLE: &str = "main_file_cache.idx255";
+pub const CACHE_PREFIX: &str = cache_prefix!();
+pub const MAIN_DATA: &str = concat!(cache_prefix!(), ".dat2");
+pub const REFERENCE_TABLE: &str = concat!(cache_prefix!(), ".idx255");
 pub const REFERENCE_TABLE_ID: u8 = 255;
 
 pub use archive::*;
 pub use index::*;
+pub use locator::*;
+pub use prefetch::*;
 pub use sector::*;
 
 use crate::codec::{Buffer, Encoded};
@@ -104,4 +115,6 @@ fn is_normal<T: Send + Sync + Sized + Unpin>() {}
 #[test]
 fn normal_types() {
     is_normal::<Dat2>();
+    is_normal::<ArchiveCache>();
+    is_normal::<Prefetcher>();
 }
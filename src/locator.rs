@@ -0,0 +1,189 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+
+use crate::{error::ReadError, CACHE_PREFIX, REFERENCE_TABLE_ID};
+
+const DEFAULT_SUBDIRECTORIES: [&str; 4] = ["", "cache", "LIVE", "cache/LIVE"];
+
+/// All of the files that make up a cache on disk.
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct CacheFiles {
+    /// The directory the cache files were found in.
+    pub root: PathBuf,
+    pub dat2: PathBuf,
+    /// Every index file keyed by its id, including the reference table.
+    pub indices: BTreeMap<u8, PathBuf>,
+}
+
+/// Probes a path for a cache in one of the known directory layouts.
+///
+/// Caches are not always stored as a flat directory of `main_file_cache.*` files. Some come
+/// in a `cache/` subfolder, some launchers put them in a `LIVE/` subdirectory and others
+/// rename the prefix or uppercase the extensions. The locator looks through the given path
+/// and its known subdirectories, in order, and returns the first complete cache it finds.
+///
+/// # Example
+///
+/// ```
+/// use runefs::{CacheLocator, Indices};
+///
+/// # fn main() -> Result<(), runefs::Error> {
+/// let files = CacheLocator::new()
+///     .subdirectory("osrs_cache")
+///     .locate("./data")?;
+///
+/// let indices = Indices::from_files(&files)?;
+///
+/// assert_eq!(indices.count(), 22);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct CacheLocator {
+    prefix: String,
+    data_file: Option<String>,
+    subdirectories: Vec<PathBuf>,
+}
+
+impl Default for CacheLocator {
+    fn default() -> Self {
+        Self {
+            prefix: CACHE_PREFIX.to_owned(),
+            data_file: None,
+            subdirectories: DEFAULT_SUBDIRECTORIES.iter().map(PathBuf::from).collect(),
+        }
+    }
+}
+
+impl CacheLocator {
+    /// Creates a locator that looks for `main_file_cache.*` files in the given path and
+    /// its `cache/`, `LIVE/` and `cache/LIVE/` subdirectories.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Overrides the file name prefix, `main_file_cache` by default.
+    pub fn prefix<S: Into<String>>(mut self, prefix: S) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Overrides the full name of the data file, `<prefix>.dat2` by default.
+    pub fn data_file<S: Into<String>>(mut self, name: S) -> Self {
+        self.data_file = Some(name.into());
+        self
+    }
+
+    /// Adds a subdirectory to probe after the default ones.
+    pub fn subdirectory<P: AsRef<Path>>(mut self, path: P) -> Self {
+        self.subdirectories.push(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// Searches the path for a cache.
+    ///
+    /// A directory is only accepted when it holds both the data file and the reference table.
+    /// File names are matched case-insensitively.
+    ///
+    /// # Errors
+    ///
+    /// Returns [`ReadError::CacheNotFound`](crate::error::ReadError::CacheNotFound) when none
+    /// of the probed directories contain a cache. Other I/O errors while reading a directory
+    /// are passed through.
+    pub fn locate<P: AsRef<Path>>(&self, path: P) -> crate::Result<CacheFiles> {
+        let path = path.as_ref();
+
+        for subdirectory in &self.subdirectories {
+            let root = path.join(subdirectory);
+            if !root.is_dir() {
+                continue;
+            }
+
+            if let Some(files) = self.probe(&root)? {
+                return Ok(files);
+            }
+        }
+
+        Err(ReadError::CacheNotFound(path.to_path_buf()).into())
+    }
+
+    fn probe(&self, root: &Path) -> crate::Result<Option<CacheFiles>> {
+        let data_file = self
+            .data_file
+            .clone()
+            .unwrap_or_else(|| format!("{}.dat2", self.prefix));
+        let mut dat2 = None;
+        let mut indices = BTreeMap::new();
+
+        for entry in std::fs::read_dir(root)? {
+            let path = entry?.path();
+            if !path.is_file() {
+                continue;
+            }
+
+            let name = match path.file_name().and_then(OsStr::to_str) {
+                Some(name) => name,
+                None => continue,
+            };
+
+            if name.eq_ignore_ascii_case(&data_file) {
+                dat2 = Some(path);
+            } else if let Some(index_id) = index_id(name, &self.prefix) {
+                indices.insert(index_id, path);
+            }
+        }
+
+        match dat2 {
+            Some(dat2) if indices.contains_key(&REFERENCE_TABLE_ID) => Ok(Some(CacheFiles {
+                root: root.to_path_buf(),
+                dat2,
+                indices,
+            })),
+            _ => Ok(None),
+        }
+    }
+}
+
+/// Extracts the index id from a `<prefix>.idx#` file name, ignoring case.
+///
+/// Only plain decimal ids are accepted, a sign or leading zero would let a stray file
+/// shadow the real index with the same id.
+fn index_id(name: &str, prefix: &str) -> Option<u8> {
+    let (stem, extension) = name.rsplit_once('.')?;
+    if !stem.eq_ignore_ascii_case(prefix) || !extension.get(..3)?.eq_ignore_ascii_case("idx") {
+        return None;
+    }
+
+    let id = extension.get(3..)?;
+    if id.is_empty() || !id.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    if id.len() > 1 && id.starts_with('0') {
+        return None;
+    }
+
+    id.parse().ok()
+}
+
+#[test]
+fn parse_index_file_name() {
+    assert_eq!(index_id("main_file_cache.idx2", CACHE_PREFIX), Some(2));
+    assert_eq!(index_id("MAIN_FILE_CACHE.IDX255", CACHE_PREFIX), Some(255));
+    assert_eq!(index_id("custom.idx17", "custom"), Some(17));
+    assert_eq!(index_id("main_file_cache.idx256", CACHE_PREFIX), None);
+    assert_eq!(index_id("main_file_cache.idx", CACHE_PREFIX), None);
+    assert_eq!(index_id("main_file_cache.dat2", CACHE_PREFIX), None);
+    assert_eq!(index_id("other.idx2", CACHE_PREFIX), None);
+    assert_eq!(index_id("main_file_cache.idx0", CACHE_PREFIX), Some(0));
+    assert_eq!(index_id("main_file_cache.idx+2", CACHE_PREFIX), None);
+    assert_eq!(index_id("main_file_cache.idx02", CACHE_PREFIX), None);
+    assert_eq!(index_id("main_file_cache.idx00", CACHE_PREFIX), None);
+    assert_eq!(index_id("main_file_cache.idx-1", CACHE_PREFIX), None);
+}
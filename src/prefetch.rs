@@ -0,0 +1,408 @@
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+        Arc, Condvar, Mutex,
+    },
+    thread::{self, JoinHandle},
+};
+
+use crate::{error::ReadError, Dat2, Indices};
+
+type ArchiveKey = (u8, u32);
+
+#[derive(Debug)]
+struct DecodedArchives {
+    capacity: usize,
+    tick: u64,
+    archives: HashMap<ArchiveKey, (Arc<[u8]>, u64)>,
+    recency: BTreeMap<u64, ArchiveKey>,
+}
+
+impl DecodedArchives {
+    fn touch(&mut self, key: ArchiveKey) -> Option<Arc<[u8]>> {
+        let (data, last_used) = self.archives.get_mut(&key)?;
+        self.recency.remove(last_used);
+        self.tick += 1;
+        *last_used = self.tick;
+        self.recency.insert(self.tick, key);
+
+        Some(Arc::clone(data))
+    }
+
+    fn remove(&mut self, key: ArchiveKey) -> Option<Arc<[u8]>> {
+        let (data, last_used) = self.archives.remove(&key)?;
+        self.recency.remove(&last_used);
+
+        Some(data)
+    }
+
+    fn evict(&mut self) {
+        while self.archives.len() > self.capacity {
+            match self.recency.pop_first() {
+                Some((_, key)) => self.archives.remove(&key),
+                None => break,
+            };
+        }
+    }
+}
+
+/// A thread-safe, size bounded cache of decoded archives.
+///
+/// The cache holds at most `capacity` archives, once it is full inserting evicts the least
+/// recently used archive. Both [`get`](ArchiveCache::get) and [`insert`](ArchiveCache::insert)
+/// count as a use.
+///
+/// Cloning an `ArchiveCache` is cheap, every clone refers to the same underlying storage.
+/// The decoded data is kept behind an `Arc` so handing it out never copies the archive.
+#[derive(Clone, Debug)]
+pub struct ArchiveCache(Arc<Mutex<DecodedArchives>>);
+
+impl ArchiveCache {
+    /// Creates a cache that holds at most `capacity` archives.
+    ///
+    /// A capacity of zero disables the cache, nothing that is inserted will be kept.
+    pub fn new(capacity: usize) -> Self {
+        Self(Arc::new(Mutex::new(DecodedArchives {
+            capacity,
+            tick: 0,
+            archives: HashMap::new(),
+            recency: BTreeMap::new(),
+        })))
+    }
+
+    pub fn get(&self, index_id: u8, archive_id: u32) -> Option<Arc<[u8]>> {
+        self.0
+            .lock()
+            .expect("archive cache poisoned")
+            .touch((index_id, archive_id))
+    }
+
+    /// Checks if an archive is cached without marking it as used.
+    pub fn contains(&self, index_id: u8, archive_id: u32) -> bool {
+        self.0
+            .lock()
+            .expect("archive cache poisoned")
+            .archives
+            .contains_key(&(index_id, archive_id))
+    }
+
+    /// Inserts an archive, evicting the least recently used archives when the cache is full.
+    ///
+    /// Returns the archive that was previously cached under the same ids.
+    pub fn insert(&self, index_id: u8, archive_id: u32, data: Arc<[u8]>) -> Option<Arc<[u8]>> {
+        let mut cache = self.0.lock().expect("archive cache poisoned");
+        let key = (index_id, archive_id);
+        let previous = cache.remove(key);
+
+        cache.tick += 1;
+        let tick = cache.tick;
+        cache.archives.insert(key, (data, tick));
+        cache.recency.insert(tick, key);
+        cache.evict();
+
+        previous
+    }
+
+    pub fn remove(&self, index_id: u8, archive_id: u32) -> Option<Arc<[u8]>> {
+        self.0
+            .lock()
+            .expect("archive cache poisoned")
+            .remove((index_id, archive_id))
+    }
+
+    pub fn clear(&self) {
+        let mut cache = self.0.lock().expect("archive cache poisoned");
+        cache.archives.clear();
+        cache.recency.clear();
+    }
+
+    /// The maximum amount of archives this cache holds.
+    pub fn capacity(&self) -> usize {
+        self.0.lock().expect("archive cache poisoned").capacity
+    }
+
+    pub fn len(&self) -> usize {
+        let cache = self.0.lock().expect("archive cache poisoned");
+        cache.archives.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Fetches a decoded archive from the cache, reading and decoding it on a miss.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the index or archive does not exist, or when the archive could not be
+    /// read or decoded. Encrypted archives can not be decoded without their keys and will
+    /// always fail.
+    pub fn get_or_load(
+        &self,
+        dat2: &Dat2,
+        indices: &Indices,
+        index_id: u8,
+        archive_id: u32,
+    ) -> crate::Result<Arc<[u8]>> {
+        if let Some(data) = self.get(index_id, archive_id) {
+            return Ok(data);
+        }
+
+        let data = load(dat2, indices, index_id, archive_id)?;
+        self.insert(index_id, archive_id, Arc::clone(&data));
+
+        Ok(data)
+    }
+}
+
+fn load(dat2: &Dat2, indices: &Indices, index_id: u8, archive_id: u32) -> crate::Result<Arc<[u8]>> {
+    let archive_ref = indices
+        .get(&index_id)
+        .ok_or(ReadError::IndexNotFound(index_id))?
+        .archive_refs
+        .get(&archive_id)
+        .ok_or(ReadError::ArchiveNotFound {
+            idx: index_id,
+            arc: archive_id,
+        })?;
+    let buffer = dat2.read(archive_ref)?.decode()?;
+
+    Ok(buffer.finalize().into())
+}
+
+struct Hint {
+    index_id: u8,
+    archive_id: u32,
+    generation: usize,
+}
+
+#[derive(Debug, Default)]
+struct Pause {
+    paused: Mutex<bool>,
+    condvar: Condvar,
+}
+
+impl Pause {
+    fn set(&self, paused: bool) {
+        *self.paused.lock().expect("prefetch pause poisoned") = paused;
+        self.condvar.notify_all();
+    }
+
+    fn wait(&self) {
+        let paused = self.paused.lock().expect("prefetch pause poisoned");
+        let _paused = self
+            .condvar
+            .wait_while(paused, |paused| *paused)
+            .expect("prefetch pause poisoned");
+    }
+}
+
+/// Warms an [`ArchiveCache`](ArchiveCache) on a background thread.
+///
+/// Applications that know which archives they need next, like adjacent map squares or model
+/// dependencies, can queue them as hints. The worker reads every hinted archive from the
+/// `Dat2` file, which pulls its pages into memory, and stores the decoded archive in the cache.
+/// The capacity of the cache bounds how much the worker keeps around, prefetching more
+/// archives than fit in the cache evicts the least recently used ones.
+///
+/// The queue is bounded, [`prefetch`](Prefetcher::prefetch) blocks while it is full and
+/// [`try_prefetch`](Prefetcher::try_prefetch) rejects the hint instead. Pending hints can be
+/// dropped with [`cancel`](Prefetcher::cancel), for example when the player teleports.
+/// Hints that fail to load, such as encrypted map archives, are skipped.
+///
+/// Dropping the `Prefetcher` cancels any pending hints and waits for the worker to stop, use
+/// [`finish`](Prefetcher::finish) to load the pending hints first. Only `finish` reports a
+/// panic of the worker, a dropped `Prefetcher` silently discards it.
+///
+/// # Example
+///
+/// ```
+/// use std::sync::Arc;
+/// use runefs::{ArchiveCache, Dat2, Indices, Prefetcher};
+///
+/// # fn main() -> Result<(), runefs::Error> {
+/// let dat2 = Arc::new(Dat2::new("./data/osrs_cache/main_file_cache.dat2")?);
+/// let indices = Arc::new(Indices::new("./data/osrs_cache")?);
+/// let cache = ArchiveCache::new(256);
+///
+/// let prefetcher = Prefetcher::new(dat2, indices, cache.clone(), 64)?;
+/// prefetcher.prefetch(2, 10);
+/// prefetcher.finish().expect("prefetch worker panicked");
+///
+/// assert!(cache.contains(2, 10));
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct Prefetcher {
+    sender: Option<SyncSender<Hint>>,
+    generation: Arc<AtomicUsize>,
+    pause: Arc<Pause>,
+    worker: Option<JoinHandle<()>>,
+    cache: ArchiveCache,
+}
+
+impl Prefetcher {
+    /// Spawns the background worker with room for `capacity` pending hints.
+    ///
+    /// # Errors
+    ///
+    /// Fails when the worker thread could not be spawned.
+    pub fn new(
+        dat2: Arc<Dat2>,
+        indices: Arc<Indices>,
+        cache: ArchiveCache,
+        capacity: usize,
+    ) -> crate::Result<Self> {
+        let (sender, receiver) = mpsc::sync_channel(capacity);
+        let generation = Arc::new(AtomicUsize::new(0));
+        let pause = Arc::new(Pause::default());
+        let worker = {
+            let generation = Arc::clone(&generation);
+            let pause = Arc::clone(&pause);
+            let cache = cache.clone();
+            thread::Builder::new()
+                .name("runefs-prefetch".to_owned())
+                .spawn(move || work(&dat2, &indices, &cache, &generation, &pause, receiver))?
+        };
+
+        Ok(Self {
+            sender: Some(sender),
+            generation,
+            pause,
+            worker: Some(worker),
+            cache,
+        })
+    }
+
+    /// Queues a hint, blocking while the queue is full.
+    ///
+    /// Returns `false` if the worker is no longer running, for example because it panicked.
+    /// Use [`finish`](Prefetcher::finish) to retrieve the panic.
+    pub fn prefetch(&self, index_id: u8, archive_id: u32) -> bool {
+        match &self.sender {
+            Some(sender) => sender.send(self.hint(index_id, archive_id)).is_ok(),
+            None => false,
+        }
+    }
+
+    /// Queues a hint without blocking.
+    ///
+    /// Returns `false` if the queue is full or the worker is no longer running.
+    pub fn try_prefetch(&self, index_id: u8, archive_id: u32) -> bool {
+        match &self.sender {
+            Some(sender) => match sender.try_send(self.hint(index_id, archive_id)) {
+                Ok(()) => true,
+                Err(TrySendError::Full(_) | TrySendError::Disconnected(_)) => false,
+            },
+            None => false,
+        }
+    }
+
+    /// Drops every hint queued so far, the archive currently being loaded still completes.
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Stops the worker from taking on new hints until [`resume`](Prefetcher::resume) is called.
+    ///
+    /// Hints can still be queued while paused, until the queue is full.
+    pub fn pause(&self) {
+        self.pause.set(true);
+    }
+
+    pub fn resume(&self) {
+        self.pause.set(false);
+    }
+
+    /// Stops accepting hints and waits for the worker to load every pending hint.
+    ///
+    /// # Errors
+    ///
+    /// Returns the panic payload if the worker panicked.
+    pub fn finish(mut self) -> thread::Result<()> {
+        self.join()
+    }
+
+    /// The cache the worker stores its decoded archives in.
+    pub const fn cache(&self) -> &ArchiveCache {
+        &self.cache
+    }
+
+    fn hint(&self, index_id: u8, archive_id: u32) -> Hint {
+        Hint {
+            index_id,
+            archive_id,
+            generation: self.generation.load(Ordering::SeqCst),
+        }
+    }
+
+    fn join(&mut self) -> thread::Result<()> {
+        self.resume();
+        self.sender.take();
+
+        match self.worker.take() {
+            Some(worker) => worker.join(),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for Prefetcher {
+    fn drop(&mut self) {
+        self.cancel();
+        let _ = self.join();
+    }
+}
+
+fn work(
+    dat2: &Dat2,
+    indices: &Indices,
+    cache: &ArchiveCache,
+    generation: &AtomicUsize,
+    pause: &Pause,
+    receiver: Receiver<Hint>,
+) {
+    for hint in receiver {
+        pause.wait();
+
+        if hint.generation != generation.load(Ordering::SeqCst)
+            || cache.contains(hint.index_id, hint.archive_id)
+        {
+            continue;
+        }
+
+        if let Ok(data) = load(dat2, indices, hint.index_id, hint.archive_id) {
+            cache.insert(hint.index_id, hint.archive_id, data);
+        }
+    }
+}
+
+#[test]
+fn archive_cache_eviction() {
+    let cache = ArchiveCache::new(2);
+    cache.insert(2, 0, Arc::from(&[0][..]));
+    cache.insert(2, 1, Arc::from(&[1][..]));
+
+    assert!(cache.get(2, 0).is_some());
+    cache.insert(2, 2, Arc::from(&[2][..]));
+
+    assert_eq!(cache.len(), 2);
+    assert!(cache.contains(2, 0));
+    assert!(!cache.contains(2, 1));
+    assert!(cache.contains(2, 2));
+
+    let previous = cache.insert(2, 2, Arc::from(&[3][..]));
+    assert_eq!(previous, Some(Arc::from(&[2][..])));
+    assert_eq!(cache.len(), 2);
+}
+
+#[test]
+fn archive_cache_disabled() {
+    let cache = ArchiveCache::new(0);
+    cache.insert(2, 0, Arc::from(&[0][..]));
+
+    assert!(cache.is_empty());
+}
@@ -110,17 +110,12 @@ impl<'a> SectorHeader {
     }
 }
 
-impl Default for SectorHeaderSize {
-    fn default() -> Self {
-        Self::Normal
-    }
-}
-
 /// Used to convey a sector's header size when parsing from a raw buffer.
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
 pub enum SectorHeaderSize {
     /// 8 byte header length.
+    #[default]
     Normal,
     /// 10 byte header length.
     Expanded,
@@ -128,7 +123,7 @@ pub enum SectorHeaderSize {
 
 impl From<&ArchiveRef> for SectorHeaderSize {
     fn from(archive: &ArchiveRef) -> Self {
-        if archive.id > std::u16::MAX.into() {
+        if archive.id > u16::MAX.into() {
             Self::Expanded
         } else {
             Self::Normal
@@ -1,8 +1,13 @@
 #[cfg(test)]
 mod osrs {
+    use runefs::error::{Error, ReadError};
     use runefs::Dat2;
-    use runefs::{Index, Indices};
+    use runefs::{ArchiveCache, ArchiveFileGroup, CacheLocator, Index, Indices, Prefetcher};
+    use runefs::{MAIN_DATA, REFERENCE_TABLE};
     use std::collections::HashMap;
+    use std::fs;
+    use std::path::{Path, PathBuf};
+    use std::sync::Arc;
 
     #[test]
     fn new_indices() {
@@ -16,7 +21,7 @@ mod osrs {
 
     #[test]
     fn correct_layout() {
-        let mut map: HashMap<u8, u8> = (0..=20).into_iter().map(|i| (i, i)).collect();
+        let mut map: HashMap<u8, u8> = (0..=20).map(|i| (i, i)).collect();
         map.insert(255, 255);
 
         let indices: HashMap<u8, u8> = Indices::new("./data/osrs_cache")
@@ -44,6 +49,201 @@ mod osrs {
     fn from_path_incorrect_extension() {
         Index::from_path(2, "../data/osrs_cache/main_file_cache.idx1").unwrap();
     }
+
+    #[test]
+    fn locate_subdirectory() {
+        let files = CacheLocator::new()
+            .subdirectory("osrs_cache")
+            .locate("./data")
+            .unwrap();
+
+        assert_eq!(files.root, Path::new("./data/osrs_cache"));
+        assert_eq!(files.indices.len(), 22);
+
+        let indices = Indices::from_files(&files).unwrap();
+        assert_eq!(indices.count(), 22);
+    }
+
+    fn layout(name: &str, subdirectory: &str, rename: impl Fn(&str) -> String) -> PathBuf {
+        let root = Path::new(env!("CARGO_TARGET_TMPDIR"))
+            .join("layouts")
+            .join(name);
+        let _ = fs::remove_dir_all(&root);
+        let dir = root.join(subdirectory);
+        fs::create_dir_all(&dir).unwrap();
+
+        for file in [MAIN_DATA, "main_file_cache.idx2", REFERENCE_TABLE] {
+            let from = Path::new("./data/osrs_cache").join(file);
+            let to = dir.join(rename(file));
+            if fs::hard_link(&from, &to).is_err() {
+                fs::copy(&from, &to).unwrap();
+            }
+        }
+
+        root
+    }
+
+    #[test]
+    fn locate_default_subdirectories() {
+        for subdirectory in ["cache", "LIVE", "cache/LIVE"] {
+            let name = subdirectory.replace('/', "_");
+            let root = layout(&name, subdirectory, str::to_owned);
+            let files = CacheLocator::new().locate(&root).unwrap();
+
+            assert_eq!(files.root, root.join(subdirectory));
+            let ids: Vec<u8> = files.indices.keys().copied().collect();
+            assert_eq!(ids, vec![2, 255]);
+
+            let indices = Indices::from_files(&files).unwrap();
+            assert_eq!(indices.count(), 2);
+        }
+    }
+
+    #[test]
+    fn locate_uppercase_names() {
+        let root = layout("uppercase", "", str::to_uppercase);
+        let files = CacheLocator::new().locate(&root).unwrap();
+
+        assert_eq!(files.dat2, root.join("MAIN_FILE_CACHE.DAT2"));
+        assert_eq!(files.indices[&2], root.join("MAIN_FILE_CACHE.IDX2"));
+
+        let indices = Indices::from_files(&files).unwrap();
+        assert_eq!(indices.count(), 2);
+    }
+
+    #[test]
+    fn locate_renamed_files() {
+        let root = layout("renamed", "", |file| {
+            if file == MAIN_DATA {
+                "cache_data.dat".to_owned()
+            } else {
+                file.replace("main_file_cache", "jagex_cache")
+            }
+        });
+
+        let err = CacheLocator::new().locate(&root).unwrap_err();
+        assert!(matches!(err, Error::Read(ReadError::CacheNotFound(_))));
+
+        let files = CacheLocator::new()
+            .prefix("jagex_cache")
+            .data_file("cache_data.dat")
+            .locate(&root)
+            .unwrap();
+        assert_eq!(files.dat2, root.join("cache_data.dat"));
+        assert_eq!(files.indices[&255], root.join("jagex_cache.idx255"));
+
+        let indices = Indices::from_files(&files).unwrap();
+        assert_eq!(indices.count(), 2);
+    }
+
+    #[test]
+    fn locate_missing_cache() {
+        let err = CacheLocator::new().locate("./data").unwrap_err();
+
+        assert!(matches!(err, Error::Read(ReadError::CacheNotFound(_))));
+    }
+
+    #[test]
+    fn decode_multi_chunk_group() {
+        let dat2 = Dat2::new("./data/osrs_cache/main_file_cache.dat2").unwrap();
+        let indices = Indices::new("./data/osrs_cache").unwrap();
+        let index = indices.get(&0).unwrap();
+        let metadata = &index.metadata[0];
+
+        let buffer = dat2
+            .read(&index.archive_refs[&metadata.id])
+            .unwrap()
+            .decode()
+            .unwrap();
+        let group = ArchiveFileGroup::from_buffer(&buffer, metadata.entry_count);
+
+        assert!(group.chunks() > 1);
+        assert_eq!(group.len(), metadata.entry_count);
+
+        let data_len: usize = group.iter().map(|file| file.data.len()).sum();
+        let table_len = group.chunks() * metadata.entry_count * 4;
+        assert_eq!(data_len, buffer.len() - 1 - table_len);
+    }
+
+    fn prefetcher(cache: &ArchiveCache, capacity: usize) -> (Arc<Dat2>, Arc<Indices>, Prefetcher) {
+        let dat2 = Arc::new(Dat2::new("./data/osrs_cache/main_file_cache.dat2").unwrap());
+        let indices = Arc::new(Indices::new("./data/osrs_cache").unwrap());
+        let prefetcher =
+            Prefetcher::new(dat2.clone(), indices.clone(), cache.clone(), capacity).unwrap();
+
+        (dat2, indices, prefetcher)
+    }
+
+    #[test]
+    fn prefetch_archives() {
+        let cache = ArchiveCache::new(16);
+        let (dat2, indices, prefetcher) = prefetcher(&cache, 4);
+
+        assert!(prefetcher.prefetch(2, 10));
+        assert!(prefetcher.prefetch(2, 6));
+        assert!(prefetcher.prefetch(2, u32::MAX));
+        prefetcher.finish().unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(2, u32::MAX));
+
+        let prefetched = cache.get(2, 10).unwrap();
+        let loaded = ArchiveCache::new(1)
+            .get_or_load(&dat2, &indices, 2, 10)
+            .unwrap();
+        assert_eq!(prefetched, loaded);
+    }
+
+    #[test]
+    fn prefetch_evicts() {
+        let cache = ArchiveCache::new(2);
+        let (_, _, prefetcher) = prefetcher(&cache, 4);
+
+        for archive_id in [1, 2, 3] {
+            assert!(prefetcher.prefetch(2, archive_id));
+        }
+        prefetcher.finish().unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(!cache.contains(2, 1));
+        assert!(cache.contains(2, 2));
+        assert!(cache.contains(2, 3));
+    }
+
+    #[test]
+    fn prefetch_backpressure() {
+        let cache = ArchiveCache::new(16);
+        let (_, _, prefetcher) = prefetcher(&cache, 2);
+        prefetcher.pause();
+
+        // The paused worker holds on to at most one hint and the queue takes two, so out of
+        // four hints at least one has to be rejected. Which one depends on when the worker
+        // wakes up, so only the accepted hints are counted.
+        let queued = (1..5)
+            .filter(|&archive_id| prefetcher.try_prefetch(2, archive_id))
+            .count();
+        assert!((2..=3).contains(&queued));
+
+        prefetcher.finish().unwrap();
+        assert_eq!(cache.len(), queued);
+    }
+
+    #[test]
+    fn prefetch_cancel() {
+        let cache = ArchiveCache::new(16);
+        let (_, _, prefetcher) = prefetcher(&cache, 4);
+        prefetcher.pause();
+
+        for archive_id in [1, 2, 3] {
+            assert!(prefetcher.prefetch(2, archive_id));
+        }
+        prefetcher.cancel();
+        assert!(prefetcher.prefetch(2, 10));
+        prefetcher.finish().unwrap();
+
+        assert_eq!(cache.len(), 1);
+        assert!(cache.contains(2, 10));
+    }
 }
 
 #[cfg(all(test, feature = "rs3"))]
@@ -64,7 +264,7 @@ mod rs3 {
 
     #[test]
     fn correct_layout() {
-        let mut map: HashMap<u8, u8> = (0..=56).into_iter().map(|i| (i, i)).collect();
+        let mut map: HashMap<u8, u8> = (0..=56).map(|i| (i, i)).collect();
         map.insert(255, 255);
 
         let indices: HashMap<u8, u8> = Indices::new("./data/rs3_cache")